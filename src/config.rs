@@ -1,3 +1,4 @@
+use crate::ip::{ConsensusMode, Source};
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
@@ -6,11 +7,20 @@ pub struct Config {
     pub check_interval_secs: u64,
     pub db_path: String,
     #[serde(default)]
-    pub ip_sources: Option<Vec<String>>,
+    pub ip_sources: Option<Vec<Source>>,
+    /// IPv6-only sources; when unset, IPv6 tracking is skipped.
+    #[serde(default)]
+    pub ip_sources_v6: Option<Vec<Source>>,
+    #[serde(default)]
+    pub consensus: ConsensusMode,
     pub smtp: SmtpConfig,
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+    #[serde(default)]
+    pub startup: StartupConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct SmtpConfig {
     pub username: String,
     pub app_password: String,
@@ -25,6 +35,51 @@ pub struct SmtpConfig {
 fn default_server() -> String { "smtp.gmail.com".to_string() }
 fn default_port() -> u16 { 587 }
 
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: Option<std::collections::HashMap<String, String>>,
+    #[serde(default = "default_webhook_method")]
+    pub method: String,
+}
+
+fn default_webhook_method() -> String { "POST".to_string() }
+
+/// How to behave during startup, before the first `query_external_ip`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct StartupConfig {
+    #[serde(default)]
+    pub mode: StartupMode,
+    #[serde(default = "default_startup_timeout_secs")]
+    pub startup_timeout_secs: u64,
+    /// A `host:port` to TCP-probe instead of HEAD-requesting an IP source.
+    #[serde(default)]
+    pub probe_target: Option<String>,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        StartupConfig {
+            mode: StartupMode::default(),
+            startup_timeout_secs: default_startup_timeout_secs(),
+            probe_target: None,
+        }
+    }
+}
+
+fn default_startup_timeout_secs() -> u64 { 120 }
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupMode {
+    /// Sleep a random 3-6 minutes, then proceed regardless of connectivity.
+    #[default]
+    Jitter,
+    /// Actively retry a reachability probe with capped exponential backoff.
+    Probe,
+}
+
 /// Load config from a TOML file path.
 pub fn load_from(path: &str) -> Result<Config> {
     let text = std::fs::read_to_string(path)
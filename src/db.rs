@@ -3,6 +3,39 @@ use rusqlite::{params, Connection};
 use std::{net::IpAddr, path::Path};
 use time::OffsetDateTime;
 
+/// Which IP address family a row/lookup applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /// The family a given address belongs to.
+    pub fn of(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => IpFamily::V4,
+            IpAddr::V6(_) => IpFamily::V6,
+        }
+    }
+
+    /// Stable string form stored in the `family` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "v4",
+            IpFamily::V6 => "v6",
+        }
+    }
+
+    /// Human-readable label for logs and emails.
+    pub fn label(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "IPv4",
+            IpFamily::V6 => "IPv6",
+        }
+    }
+}
+
 /// Initialize the SQLite database (create file and tables if needed).
 pub fn init_db(db_path: &str) -> Result<Connection> {
     if let Some(parent) = Path::new(db_path).parent() {
@@ -18,18 +51,34 @@ pub fn init_db(db_path: &str) -> Result<Connection> {
         CREATE TABLE IF NOT EXISTS ip_history (
             id          INTEGER PRIMARY KEY AUTOINCREMENT,
             ip          TEXT NOT NULL,
+            family      TEXT NOT NULL DEFAULT 'v4',
             changed_at  TEXT NOT NULL
         );
         CREATE INDEX IF NOT EXISTS idx_ip_history_changed_at ON ip_history(changed_at);
         "#,
     )?;
+    migrate_add_family_column(&conn)?;
     Ok(conn)
 }
 
-/// Read the latest recorded IP from DB (if any).
-pub fn get_last_ip(conn: &Connection) -> Result<Option<IpAddr>> {
-    let mut stmt = conn.prepare("SELECT ip FROM ip_history ORDER BY id DESC LIMIT 1")?;
-    let mut rows = stmt.query([])?;
+/// Backfill the `family` column on databases created before dual-stack tracking
+/// existed; every pre-existing row is assumed to be an IPv4 entry.
+fn migrate_add_family_column(conn: &Connection) -> Result<()> {
+    let has_family: bool = conn
+        .prepare("SELECT 1 FROM pragma_table_info('ip_history') WHERE name = 'family'")?
+        .exists([])?;
+    if !has_family {
+        conn.execute_batch("ALTER TABLE ip_history ADD COLUMN family TEXT NOT NULL DEFAULT 'v4';")?;
+    }
+    Ok(())
+}
+
+/// Read the latest recorded IP for a given family from DB (if any).
+pub fn get_last_ip(conn: &Connection, family: IpFamily) -> Result<Option<IpAddr>> {
+    let mut stmt = conn.prepare(
+        "SELECT ip FROM ip_history WHERE family = ?1 ORDER BY id DESC LIMIT 1",
+    )?;
+    let mut rows = stmt.query(params![family.as_str()])?;
     if let Some(row) = rows.next()? {
         let ip_str: String = row.get(0)?;
         let ip = ip_str
@@ -41,14 +90,25 @@ pub fn get_last_ip(conn: &Connection) -> Result<Option<IpAddr>> {
     }
 }
 
-/// Save a new IP entry with timestamp.
-pub fn save_ip(conn: &Connection, ip: IpAddr) -> Result<()> {
+/// Save a new IP entry with timestamp, keyed off the caller-asserted `family` —
+/// not re-derived from `ip` — so it always lands in the same bucket `get_last_ip`
+/// was queried against, even if a misbehaving source returned the wrong protocol.
+pub fn save_ip(conn: &Connection, family: IpFamily, ip: IpAddr) -> Result<()> {
     let now = OffsetDateTime::now_utc()
         .format(&time::format_description::well_known::Rfc3339)
         .unwrap_or_else(|_| "unknown-time".into());
     conn.execute(
-        "INSERT INTO ip_history (ip, changed_at) VALUES (?1, ?2)",
-        params![ip.to_string(), now],
+        "INSERT INTO ip_history (ip, family, changed_at) VALUES (?1, ?2, ?3)",
+        params![ip.to_string(), family.as_str(), now],
     )?;
     Ok(())
 }
+
+/// Checkpoint and truncate the WAL file, folding it back into the main database.
+///
+/// Meant to be called on graceful shutdown so a truncated SMTP send or DB write
+/// mid-iteration doesn't leave an oversized or stranded `-wal` file behind.
+pub fn checkpoint_wal(conn: &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    Ok(())
+}
@@ -1,5 +1,10 @@
 use anyhow::Result;
+use futures::future::join_all;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
 use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::net::IpAddr;
 use thiserror::Error;
 
@@ -7,34 +12,158 @@ use thiserror::Error;
 pub enum IpError {
     #[error("No IP sources available")]
     NoSources,
+    #[error("Sources disagreed on external IP: {0}")]
+    Disagreement(String),
 }
 
-/// Query the current external IP by trying a list of sources in order.
-/// Returns the first successfully parsed IP.
-pub async fn query_external_ip(http: &Client, sources: Option<Vec<String>>) -> Result<IpAddr> {
-    let default_sources: Vec<String> = vec![
-        "https://api.ipify.org".to_string(),
-        "https://ifconfig.me/ip".to_string(),
-        "https://ident.me".to_string(),
-        "https://checkip.amazonaws.com".to_string(),
+/// How to reconcile the IP values reported by multiple sources.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ConsensusMode {
+    /// Return the IP from the first source that parses successfully.
+    FirstSuccess,
+    /// Query all sources concurrently; an IP wins once `min_agree` sources report it.
+    Quorum { min_agree: usize },
+    /// Query all sources concurrently; an IP wins with a strict majority of responses.
+    Majority,
+}
+
+impl Default for ConsensusMode {
+    fn default() -> Self {
+        ConsensusMode::FirstSuccess
+    }
+}
+
+/// A place to ask for our external IP: an HTTP echo service or a DNS resolver trick.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Source {
+    /// Fetch a URL and parse the response body as an `IpAddr`.
+    Http(String),
+    /// Resolve `name` against `resolver` and parse the answer as an `IpAddr`.
+    Dns {
+        name: String,
+        resolver: IpAddr,
+        #[serde(default)]
+        record: DnsRecordKind,
+    },
+}
+
+/// Which DNS record type encodes the IP in a DNS-based source.
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordKind {
+    #[default]
+    A,
+    Aaaa,
+    Txt,
+}
+
+/// Query the current external IP from a list of sources, reconciled per `consensus`.
+pub async fn query_external_ip(
+    http: &Client,
+    sources: Option<Vec<Source>>,
+    consensus: &ConsensusMode,
+) -> Result<IpAddr> {
+    let default_sources: Vec<Source> = vec![
+        Source::Http("https://api.ipify.org".to_string()),
+        Source::Http("https://ifconfig.me/ip".to_string()),
+        Source::Http("https://ident.me".to_string()),
+        Source::Http("https://checkip.amazonaws.com".to_string()),
     ];
     let list = sources.unwrap_or(default_sources);
     if list.is_empty() {
         return Err(IpError::NoSources.into());
     }
 
-    for url in list {
-        match http.get(url).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                let text = resp.text().await.unwrap_or_default();
-                let trimmed = text.trim();
-                if let Ok(ip) = trimmed.parse::<IpAddr>() {
-                    return Ok(ip);
-                }
-            }
-            _ => {}
-        }
+    match consensus {
+        ConsensusMode::FirstSuccess => query_first_success(http, list).await,
+        ConsensusMode::Quorum { min_agree } => query_by_tally(http, list, Some(*min_agree)).await,
+        ConsensusMode::Majority => query_by_tally(http, list, None).await,
     }
+}
 
+/// Try sources in order, returning the first one that parses successfully.
+async fn query_first_success(http: &Client, list: Vec<Source>) -> Result<IpAddr> {
+    for source in &list {
+        if let Some(ip) = fetch_one(http, source).await {
+            return Ok(ip);
+        }
+    }
     Err(anyhow::anyhow!("All IP sources failed or returned invalid data"))
 }
+
+/// Query all sources concurrently and require agreement before returning an IP.
+///
+/// `min_agree` of `None` means "a strict majority of responding sources".
+async fn query_by_tally(http: &Client, list: Vec<Source>, min_agree: Option<usize>) -> Result<IpAddr> {
+    let fetches = list.iter().map(|source| fetch_one(http, source));
+    let results = join_all(fetches).await;
+
+    let mut tally: HashMap<IpAddr, usize> = HashMap::new();
+    let mut responded = 0usize;
+    for ip in results.into_iter().flatten() {
+        *tally.entry(ip).or_insert(0) += 1;
+        responded += 1;
+    }
+
+    if responded == 0 {
+        return Err(anyhow::anyhow!("All IP sources failed or returned invalid data"));
+    }
+
+    let threshold = min_agree.unwrap_or(responded / 2 + 1);
+    let mut winners = tally.iter().filter(|(_, &count)| count >= threshold);
+    if let Some((&ip, _)) = winners.next() {
+        if winners.next().is_none() {
+            return Ok(ip);
+        }
+    }
+
+    let mut counts: Vec<(IpAddr, usize)> = tally.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    let summary = counts
+        .iter()
+        .map(|(ip, count)| format!("{count} said {ip}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(IpError::Disagreement(summary).into())
+}
+
+/// Fetch a single source and parse its answer as an `IpAddr`, if possible.
+async fn fetch_one(http: &Client, source: &Source) -> Option<IpAddr> {
+    match source {
+        Source::Http(url) => fetch_http(http, url).await,
+        Source::Dns { name, resolver, record } => fetch_dns(name, *resolver, *record).await,
+    }
+}
+
+async fn fetch_http(http: &Client, url: &str) -> Option<IpAddr> {
+    let resp = http.get(url).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let text = resp.text().await.ok()?;
+    text.trim().parse::<IpAddr>().ok()
+}
+
+async fn fetch_dns(name: &str, resolver: IpAddr, record: DnsRecordKind) -> Option<IpAddr> {
+    let ns_group = NameServerConfigGroup::from_ips_clear(&[resolver], 53, true);
+    let resolver_config = ResolverConfig::from_parts(None, vec![], ns_group);
+    let resolver = TokioAsyncResolver::tokio(resolver_config, ResolverOpts::default());
+
+    match record {
+        DnsRecordKind::A => {
+            let response = resolver.ipv4_lookup(name).await.ok()?;
+            response.iter().next().map(|ip| IpAddr::V4(ip.0))
+        }
+        DnsRecordKind::Aaaa => {
+            let response = resolver.ipv6_lookup(name).await.ok()?;
+            response.iter().next().map(|ip| IpAddr::V6(ip.0))
+        }
+        DnsRecordKind::Txt => {
+            let response = resolver.txt_lookup(name).await.ok()?;
+            let txt = response.iter().next()?;
+            txt.to_string().trim_matches('"').parse::<IpAddr>().ok()
+        }
+    }
+}
@@ -4,8 +4,16 @@
 pub mod config;
 pub mod ip;
 pub mod db;
+pub mod notifier;
+pub mod shutdown;
+pub mod startup;
+pub mod watch;
 
 // Re-export most used items for ergonomic main.rs imports.
-pub use config::{load_from, Config, SmtpConfig};
-pub use ip::query_external_ip;
-pub use db::{init_db, get_last_ip, save_ip};
+pub use config::{load_from, Config, SmtpConfig, WebhookConfig};
+pub use ip::{query_external_ip, ConsensusMode, Source};
+pub use db::{init_db, get_last_ip, save_ip, checkpoint_wal, IpFamily};
+pub use notifier::{EmailNotifier, IpChangeEvent, Notifier, WebhookNotifier};
+pub use shutdown::{install_signal_handlers, ExitListener};
+pub use startup::wait_for_network;
+pub use watch::watch_config;
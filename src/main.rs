@@ -1,17 +1,23 @@
 use anyhow::{Context, Result};
-use ipwatcher::{load_from, query_external_ip, get_last_ip, init_db, save_ip, Config, SmtpConfig};
+use arc_swap::ArcSwap;
+use ipwatcher::{
+    checkpoint_wal, install_signal_handlers, load_from, query_external_ip, get_last_ip, init_db,
+    save_ip, wait_for_network, watch_config, Config, EmailNotifier, ExitListener, IpChangeEvent,
+    IpFamily, Notifier, SmtpConfig, Source, WebhookNotifier,
+};
 use lettre::{
-    message::{header, Mailbox, Message},
     transport::smtp::authentication::Credentials,
-    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+    AsyncSmtpTransport, Tokio1Executor,
 };
 use reqwest::Client;
 use std::net::IpAddr;
-use time::OffsetDateTime;
-use tokio::{signal, time::{sleep, Duration}};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
 use tracing::{info, error, trace};
 use tracing_subscriber::{EnvFilter, fmt};
 
+const CONFIG_PATH: &str = "config.toml";
+
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -30,132 +36,229 @@ async fn main() -> Result<()> {
     info!("ip-watcher starting...");
     trace!("tracing initialized at TRACE level");
 
-    // ---- STARTUP JITTER: random delay 3–6 minutes to avoid boot-time network issues ----
-    let jitter_secs: u64 = rand::random_range(3*60..=6*60);
-    info!("Startup jitter: sleeping {} seconds before first network work.", jitter_secs);
-    sleep(Duration::from_secs(jitter_secs)).await;
+    // Install the signal handlers before any startup work begins: the jitter
+    // sleep alone can run for minutes, and a signal arriving before a handler
+    // exists would hit the OS default disposition and kill the process with
+    // no WAL checkpoint.
+    let mut exit = install_signal_handlers();
 
+    // 1) Load config, then watch it for hot-reload
+    let cfg = load_from(CONFIG_PATH).context("Failed to load config.toml")?;
+    let live = Arc::new(ArcSwap::from_pointee(cfg));
+    let _config_watcher = watch_config(CONFIG_PATH, live.clone())
+        .context("Failed to start config watcher")?;
 
-    // 1) Load config
-    let cfg = load_from("config.toml").context("Failed to load config.toml")?;
+    let http = Client::builder().user_agent("ip-watcher/0.2").build()?;
 
-    // 2) Init DB
-    let conn = init_db(&cfg.db_path)?;
+    let Some((conn, mut notifiers)) = startup(&http, &live, &mut exit).await? else {
+        return Ok(());
+    };
 
-    // 3) Prepare mailer & HTTP client
-    let mailer = build_mailer(&cfg.smtp)?;
-    let http = Client::builder().user_agent("ip-watcher/0.2").build()?;
+    let cfg = live.load_full();
+    info!("Entering polling loop (every {}s).", cfg.check_interval_secs.max(30));
 
-    // 4) Initial IP check
-    let current_ip = query_external_ip(&http, cfg.ip_sources.clone()).await?;
-    let last_ip = get_last_ip(&conn)?;
-
-    if last_ip.is_none() {
-        save_ip(&conn, current_ip)?;
-        send_ip_email(&mailer, &cfg.smtp, current_ip, true).await?;
-        info!("First detected external IP: {}, email sent.", current_ip);
-    } else if Some(current_ip) != last_ip {
-        save_ip(&conn, current_ip)?;
-        send_ip_email(&mailer, &cfg.smtp, current_ip, false).await?;
-        info!(
-            "External IP changed: {:?} -> {}, email sent.",
-            last_ip.unwrap(),
-            current_ip
-        );
-    } else {
-        info!("External IP unchanged: {}.", current_ip);
-    }
+    poll_loop(&http, &conn, &mut notifiers, &live, exit).await;
+
+    info!("Checkpointing WAL before exit.");
+    checkpoint_wal(&conn).context("Failed to checkpoint WAL on shutdown")?;
+
+    Ok(())
+}
 
-    // 5) Periodic loop (graceful Ctrl+C)
-    let interval = Duration::from_secs(cfg.check_interval_secs.max(30));
-    info!("Entering polling loop (every {}s).", interval.as_secs());
+/// Run the one-time startup sequence: wait for the network, open the DB, build
+/// notifiers, and perform the initial IP check.
+///
+/// Only the network wait is raced against the exit signal, since it's the one
+/// unbounded, potentially minutes-long step (jitter sleep or active probe).
+/// Once it completes, DB init, notifier construction, and the initial IP
+/// check (which can send a real SMTP/webhook notification) run to
+/// completion: cancelling those mid-`.await` would truncate an in-flight
+/// notifier send and drop `conn` before `main` ever gets to checkpoint it.
+/// Returns `None` if the exit signal fires before the network wait does.
+async fn startup(
+    http: &Client,
+    live: &Arc<ArcSwap<Config>>,
+    exit: &mut ExitListener,
+) -> Result<Option<(rusqlite::Connection, Vec<Box<dyn Notifier>>)>> {
+    let cfg = live.load_full();
 
+    // Wait for the network before doing any real work (jitter sleep or active probe)
     tokio::select! {
-        _ = poll_loop(&http, &conn, &mailer, &cfg, interval) => {},
-        _ = signal::ctrl_c() => {
-            info!("\nCtrl+C received, shutting down.");
+        _ = wait_for_network(http, &cfg.startup, &probe_url(&cfg)) => {},
+        _ = &mut *exit => {
+            info!("Shutdown signal received while waiting for network; exiting before entering polling loop.");
+            return Ok(None);
         }
     }
 
-    Ok(())
+    // Init DB
+    let conn = init_db(&cfg.db_path)?;
+
+    // Prepare notifiers (email + optional webhook)
+    let notifiers = build_notifiers(&cfg, http)?;
+
+    // Initial IP check (IPv4 is required, IPv6 is best-effort)
+    let current_ip = query_external_ip(http, cfg.ip_sources.clone(), &cfg.consensus).await?;
+    check_family(&conn, &notifiers, IpFamily::V4, current_ip).await?;
+    check_v6_family(http, &conn, &notifiers, &cfg).await;
+
+    Ok(Some((conn, notifiers)))
 }
 
 async fn poll_loop(
     http: &Client,
     conn: &rusqlite::Connection,
-    mailer: &AsyncSmtpTransport<Tokio1Executor>,
-    cfg: &Config,
-    interval: Duration,
+    notifiers: &mut Vec<Box<dyn Notifier>>,
+    live: &Arc<ArcSwap<Config>>,
+    mut exit: ExitListener,
 ) {
+    let mut smtp = live.load().smtp.clone();
+    let mut webhook = live.load().webhook.clone();
+
     loop {
-        sleep(interval).await;
-
-        match query_external_ip(http, cfg.ip_sources.clone()).await {
-            Ok(ip) => match get_last_ip(conn) {
-                Ok(last) => {
-                    if Some(ip) != last {
-                        if let Err(e) = save_ip(conn, ip) {
-                            error!("Failed to save IP: {e:#}");
-                            continue;
-                        }
-                        if let Err(e) = send_ip_email(mailer, &cfg.smtp, ip, false).await {
-                            error!("Failed to send email: {e:#}");
-                        } else {
-                            info!("IP changed, email sent: {}", ip);
-                        }
-                    } else {
-                        info!("IP unchanged: {}", ip);
-                    }
+        let cfg = live.load_full();
+        tokio::select! {
+            _ = sleep(Duration::from_secs(cfg.check_interval_secs.max(30))) => {},
+            _ = &mut exit => {
+                info!("Shutdown signal received, exiting polling loop.");
+                return;
+            }
+        }
+
+        if cfg.smtp != smtp || cfg.webhook != webhook {
+            info!("Notification config changed, rebuilding notifiers.");
+            match build_notifiers(&cfg, http) {
+                Ok(new_notifiers) => {
+                    *notifiers = new_notifiers;
+                    smtp = cfg.smtp.clone();
+                    webhook = cfg.webhook.clone();
                 }
-                Err(e) => error!("DB read error: {e:#}"),
-            },
-            Err(e) => error!("External IP query failed: {e:#}"),
+                Err(e) => error!("Failed to rebuild notifiers from reloaded config: {e:#}"),
+            }
         }
+
+        match query_external_ip(http, cfg.ip_sources.clone(), &cfg.consensus).await {
+            Ok(ip) => {
+                if let Err(e) = check_family(conn, notifiers, IpFamily::V4, ip).await {
+                    error!("IPv4 check failed: {e:#}");
+                }
+            }
+            Err(e) => error!("External IPv4 query failed: {e:#}"),
+        }
+
+        check_v6_family(http, conn, notifiers, &cfg).await;
     }
 }
 
-fn build_mailer(cfg: &SmtpConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
-    // Be tolerant of spaces pasted into the app password
-    let creds = Credentials::new(cfg.username.clone(), cfg.app_password.replace(' ', ""));
-    let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.server)?
-        .port(cfg.port)
-        .credentials(creds)
-        .build();
-    Ok(mailer)
+/// Pick a URL for the HTTP connectivity probe: the first configured HTTP source,
+/// or a sane default if none is configured (or only DNS sources are).
+fn probe_url(cfg: &Config) -> String {
+    cfg.ip_sources
+        .as_ref()
+        .into_iter()
+        .flatten()
+        .find_map(|source| match source {
+            Source::Http(url) => Some(url.clone()),
+            Source::Dns { .. } => None,
+        })
+        .unwrap_or_else(|| "https://api.ipify.org".to_string())
 }
 
-async fn send_ip_email(
-    mailer: &AsyncSmtpTransport<Tokio1Executor>,
-    cfg: &SmtpConfig,
+/// Build the active notification channels: email always, webhook if configured.
+fn build_notifiers(cfg: &Config, http: &Client) -> Result<Vec<Box<dyn Notifier>>> {
+    let mailer = build_mailer(&cfg.smtp)?;
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(EmailNotifier {
+        mailer,
+        cfg: cfg.smtp.clone(),
+    })];
+    if let Some(webhook) = &cfg.webhook {
+        notifiers.push(Box::new(WebhookNotifier {
+            http: http.clone(),
+            cfg: webhook.clone(),
+        }));
+    }
+    Ok(notifiers)
+}
+
+/// Query the configured IPv6-only sources (if any) and reconcile the result.
+///
+/// IPv6 support is optional, so failures here are logged rather than fatal.
+async fn check_v6_family(
+    http: &Client,
+    conn: &rusqlite::Connection,
+    notifiers: &[Box<dyn Notifier>],
+    cfg: &Config,
+) {
+    let Some(sources) = cfg.ip_sources_v6.clone() else {
+        return;
+    };
+    match query_external_ip(http, Some(sources), &cfg.consensus).await {
+        Ok(ip) => {
+            if let Err(e) = check_family(conn, notifiers, IpFamily::V6, ip).await {
+                error!("IPv6 check failed: {e:#}");
+            }
+        }
+        Err(e) => error!("External IPv6 query failed: {e:#}"),
+    }
+}
+
+/// Compare a freshly queried IP for `family` against the last recorded one, saving
+/// and notifying on first detection or on change. One notifier failing does not
+/// stop the others from running.
+async fn check_family(
+    conn: &rusqlite::Connection,
+    notifiers: &[Box<dyn Notifier>],
+    family: IpFamily,
     ip: IpAddr,
-    first_time: bool,
 ) -> Result<()> {
-    let subject = if first_time {
-        format!("[IP Watcher] First external IP detected: {}", ip)
+    if IpFamily::of(ip) != family {
+        error!(
+            "Expected an {} address but source returned {ip}; treating as a failed query.",
+            family.label()
+        );
+        return Ok(());
+    }
+
+    let last_ip = get_last_ip(conn, family)?;
+    let first_detect = last_ip.is_none();
+
+    if first_detect || Some(ip) != last_ip {
+        save_ip(conn, family, ip)?;
+
+        let event = IpChangeEvent {
+            family,
+            old: last_ip,
+            new: ip,
+            first_detect,
+        };
+        for notifier in notifiers {
+            if let Err(e) = notifier.notify(&event).await {
+                error!("Notifier failed: {e:#}");
+            }
+        }
+
+        if first_detect {
+            info!("First detected external {} IP: {}.", family.label(), ip);
+        } else {
+            info!(
+                "External {} IP changed: {:?} -> {}.",
+                family.label(),
+                last_ip.unwrap(),
+                ip
+            );
+        }
     } else {
-        format!("[IP Watcher] External IP changed: {}", ip)
-    };
-    let html = format!(
-        r#"<p>Time: {time}</p>
-<p>Current external IP: <b>{ip}</b></p>
-<p>This email was sent automatically by ip-watcher.</p>"#,
-        time = now_iso(),
-        ip = ip
-    );
-
-    let email = Message::builder()
-        .from(Mailbox::new(None, cfg.from.parse()?))
-        .to(Mailbox::new(None, cfg.to.parse()?))
-        .subject(subject)
-        .header(header::ContentType::TEXT_HTML)
-        .body(html)?;
-
-    mailer.send(email).await.context("SMTP send failed")?;
+        info!("External {} IP unchanged: {}.", family.label(), ip);
+    }
     Ok(())
 }
 
-fn now_iso() -> String {
-    OffsetDateTime::now_utc()
-        .format(&time::format_description::well_known::Rfc3339)
-        .unwrap_or_else(|_| "unknown-time".into())
+fn build_mailer(cfg: &SmtpConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    // Be tolerant of spaces pasted into the app password
+    let creds = Credentials::new(cfg.username.clone(), cfg.app_password.replace(' ', ""));
+    let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.server)?
+        .port(cfg.port)
+        .credentials(creds)
+        .build();
+    Ok(mailer)
 }
@@ -0,0 +1,123 @@
+use crate::config::{SmtpConfig, WebhookConfig};
+use crate::db::IpFamily;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lettre::{
+    message::{header, Mailbox, Message},
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+};
+use reqwest::Client;
+use serde::Serialize;
+use std::net::IpAddr;
+use time::OffsetDateTime;
+
+/// A first-detection or change of the external IP, worth telling someone about.
+#[derive(Debug, Clone)]
+pub struct IpChangeEvent {
+    pub family: IpFamily,
+    pub old: Option<IpAddr>,
+    pub new: IpAddr,
+    pub first_detect: bool,
+}
+
+/// Something that can be told about an `IpChangeEvent`. Implementations should
+/// not assume other notifiers succeeded or even ran.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, event: &IpChangeEvent) -> Result<()>;
+}
+
+/// Sends HTML email via SMTP using the mailer built from `cfg`.
+pub struct EmailNotifier {
+    pub mailer: AsyncSmtpTransport<Tokio1Executor>,
+    pub cfg: SmtpConfig,
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, event: &IpChangeEvent) -> Result<()> {
+        let subject = if event.first_detect {
+            format!(
+                "[IP Watcher] First external {} IP detected: {}",
+                event.family.label(),
+                event.new
+            )
+        } else {
+            format!(
+                "[IP Watcher] External {} IP changed: {}",
+                event.family.label(),
+                event.new
+            )
+        };
+        let html = format!(
+            r#"<p>Time: {time}</p>
+<p>Current external {family} IP: <b>{ip}</b></p>
+<p>This email was sent automatically by ip-watcher.</p>"#,
+            time = now_iso(),
+            family = event.family.label(),
+            ip = event.new
+        );
+
+        let email = Message::builder()
+            .from(Mailbox::new(None, self.cfg.from.parse()?))
+            .to(Mailbox::new(None, self.cfg.to.parse()?))
+            .subject(subject)
+            .header(header::ContentType::TEXT_HTML)
+            .body(html)?;
+
+        self.mailer.send(email).await.context("SMTP send failed")?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload {
+    event: &'static str,
+    old: Option<String>,
+    new: String,
+    family: &'static str,
+    time: String,
+}
+
+/// Posts a JSON payload describing the change to a configured webhook URL.
+pub struct WebhookNotifier {
+    pub http: Client,
+    pub cfg: WebhookConfig,
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &IpChangeEvent) -> Result<()> {
+        let payload = WebhookPayload {
+            event: "ip_changed",
+            old: event.old.map(|ip| ip.to_string()),
+            new: event.new.to_string(),
+            family: event.family.as_str(),
+            time: now_iso(),
+        };
+
+        let method = self
+            .cfg
+            .method
+            .parse::<reqwest::Method>()
+            .unwrap_or(reqwest::Method::POST);
+        let mut req = self.http.request(method, &self.cfg.url).json(&payload);
+        if let Some(headers) = &self.cfg.headers {
+            for (name, value) in headers {
+                req = req.header(name, value);
+            }
+        }
+
+        let resp = req.send().await.context("Webhook request failed")?;
+        if !resp.status().is_success() {
+            anyhow::bail!("Webhook returned status {}", resp.status());
+        }
+        Ok(())
+    }
+}
+
+fn now_iso() -> String {
+    OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_else(|_| "unknown-time".into())
+}
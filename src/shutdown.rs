@@ -0,0 +1,35 @@
+use tokio::sync::oneshot;
+use tracing::info;
+
+/// Fires once when a shutdown signal arrives. Consumers `select!` on this
+/// alongside their sleep/work future and exit at the next safe point.
+pub type ExitListener = oneshot::Receiver<()>;
+
+/// Spawn a task that fires the returned `ExitListener` on Ctrl+C or, on Unix,
+/// SIGTERM, so the process can shut down deterministically under systemd.
+pub fn install_signal_handlers() -> ExitListener {
+    let (tx, rx) = oneshot::channel();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        let _ = tx.send(());
+    });
+    rx
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm =
+        signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => info!("Ctrl+C received, shutting down."),
+        _ = sigterm.recv() => info!("SIGTERM received, shutting down."),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+    info!("Ctrl+C received, shutting down.");
+}
@@ -0,0 +1,76 @@
+use crate::config::{StartupConfig, StartupMode};
+use reqwest::Client;
+use std::time::Duration;
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::{sleep, timeout, Instant};
+use tracing::{debug, info, warn};
+
+/// Wait until the network looks reachable, per `cfg.mode`.
+///
+/// In `Jitter` mode this is the original fixed random sleep. In `Probe` mode it
+/// retries a lightweight reachability check (TCP connect to `cfg.probe_target`,
+/// or an HTTP HEAD against `default_probe_url` if unset) with capped exponential
+/// backoff, proceeding as soon as one probe succeeds or `startup_timeout_secs`
+/// elapses.
+pub async fn wait_for_network(http: &Client, cfg: &StartupConfig, default_probe_url: &str) {
+    match cfg.mode {
+        StartupMode::Jitter => jitter_sleep().await,
+        StartupMode::Probe => probe_until_reachable(http, cfg, default_probe_url).await,
+    }
+}
+
+async fn jitter_sleep() {
+    let jitter_secs: u64 = rand::random_range(3 * 60..=6 * 60);
+    info!("Startup jitter: sleeping {} seconds before first network work.", jitter_secs);
+    sleep(Duration::from_secs(jitter_secs)).await;
+}
+
+async fn probe_until_reachable(http: &Client, cfg: &StartupConfig, default_probe_url: &str) {
+    let deadline = Duration::from_secs(cfg.startup_timeout_secs);
+    let start = Instant::now();
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        if probe_once(http, cfg, default_probe_url).await {
+            info!("Network reachable, proceeding.");
+            return;
+        }
+        if start.elapsed() >= deadline {
+            warn!("Startup probe timed out after {:?}; proceeding anyway.", deadline);
+            return;
+        }
+
+        let jittered = backoff + Duration::from_millis(rand::random_range(0..250));
+        let wait = jittered.min(deadline.saturating_sub(start.elapsed()));
+        debug!("Network probe failed, retrying in {:?}.", wait);
+        sleep(wait).await;
+        backoff = (backoff * 2).min(Duration::from_secs(60));
+    }
+}
+
+async fn probe_once(http: &Client, cfg: &StartupConfig, default_probe_url: &str) -> bool {
+    match &cfg.probe_target {
+        Some(target) => tcp_probe(target).await,
+        None => http_probe(http, default_probe_url).await,
+    }
+}
+
+async fn tcp_probe(host_port: &str) -> bool {
+    let Ok(Ok(mut addrs)) = timeout(Duration::from_secs(5), lookup_host(host_port)).await else {
+        return false;
+    };
+    let Some(addr) = addrs.next() else {
+        return false;
+    };
+    matches!(
+        timeout(Duration::from_secs(5), TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn http_probe(http: &Client, url: &str) -> bool {
+    match timeout(Duration::from_secs(5), http.head(url).send()).await {
+        Ok(Ok(resp)) => resp.status().is_success() || resp.status().is_redirection(),
+        _ => false,
+    }
+}
@@ -0,0 +1,67 @@
+use crate::config::{load_from, Config};
+use anyhow::{anyhow, Result};
+use arc_swap::ArcSwap;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Watch `path` for changes and hot-swap `live` with the freshly parsed config.
+///
+/// File-save events are debounced (editors often emit several modify events per
+/// save), and a config that fails to parse is logged and discarded rather than
+/// replacing the last known-good one. The returned `RecommendedWatcher` must be
+/// kept alive for the duration of the watch; dropping it stops notifications.
+///
+/// The parent directory is watched rather than `path` itself, and events are
+/// filtered by filename: editors and atomic config deployers commonly replace
+/// the file (temp-file + rename) instead of writing in place, which shows up
+/// as a Remove/Create pair rather than a Modify — and on Linux, inotify watches
+/// a file by inode, so a rename would otherwise invalidate the watch outright.
+pub fn watch_config(path: &str, live: Arc<ArcSwap<Config>>) -> Result<RecommendedWatcher> {
+    let path = Path::new(path);
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name: OsString = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Config path {} has no file name", path.display()))?
+        .to_os_string();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event)
+            if !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) =>
+        {
+            let _ = tx.send(());
+        }
+        Ok(_) => {}
+        Err(e) => error!("Config watcher error: {e:#}"),
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    let path = path.display().to_string();
+    tokio::spawn(async move {
+        const DEBOUNCE: Duration = Duration::from_millis(500);
+        while rx.recv().await.is_some() {
+            // Coalesce any further events that arrive within the debounce window.
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+
+            match load_from(&path) {
+                Ok(new_cfg) => {
+                    live.store(Arc::new(new_cfg));
+                    info!("{path} changed, config reloaded.");
+                }
+                Err(e) => {
+                    error!("Failed to reload {path}: {e:#}; keeping previous config.");
+                }
+            }
+        }
+        warn!("Config watch channel closed; hot-reload disabled.");
+    });
+
+    Ok(watcher)
+}